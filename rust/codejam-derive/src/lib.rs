@@ -0,0 +1,131 @@
+//! Companion proc-macro crate to `codejam`'s `struct_groups!`. Where
+//! `struct_groups!` both declares a struct and implements `Group` for it,
+//! `#[derive(Group)]` implements `Group` for a struct the user already
+//! wrote, so it can carry its own generics, visibility, and other derives
+//! (`Serialize`, `Clone`, ...).
+//!
+//! Expects `Group`, `Format`, `StructGroupError`, and `std::error::Error` to
+//! already be in scope at the derive site (e.g. via `use codejam::data::{Group,
+//! Format, StructGroupError}; use std::error::Error;`), unlike
+//! `struct_groups!`, whose expansion names them through `$crate` paths.
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::{parse_macro_input, Data, DeriveInput, Expr, Field, Fields, Token};
+
+/// `#[group(count = expr)]` on a `Vec<T>` field: read `expr` elements of
+/// `T` instead of a single value, mirroring the `=> $size` syntax that
+/// `struct_groups!`/`load_field!` already support.
+struct GroupAttr {
+    count: Expr,
+}
+
+impl Parse for GroupAttr {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let ident: syn::Ident = input.parse()?;
+        if ident != "count" {
+            return Err(input.error("expected `count = <expr>`"));
+        }
+        input.parse::<Token![=]>()?;
+        Ok(GroupAttr {
+            count: input.parse()?,
+        })
+    }
+}
+
+fn field_count(field: &Field) -> Option<Expr> {
+    field
+        .attrs
+        .iter()
+        .find(|attr| attr.path.is_ident("group"))
+        .map(|attr| {
+            attr.parse_args::<GroupAttr>()
+                .expect("expected #[group(count = <expr>)]")
+                .count
+        })
+}
+
+/// A field of type `T` needs `T: Group` to be decodable at all, and
+/// `T::Err: Error + Send` to satisfy `StructGroupError::new`'s bound; add
+/// both for each of the struct's own type parameters, the way `serde`'s
+/// derive adds `Serialize`/`Deserialize` bounds for its generic fields.
+fn add_group_bounds(generics: &syn::Generics) -> syn::Generics {
+    let mut generics = generics.clone();
+
+    let type_params: Vec<syn::Ident> = generics
+        .params
+        .iter()
+        .filter_map(|param| match param {
+            syn::GenericParam::Type(type_param) => Some(type_param.ident.clone()),
+            _ => None,
+        })
+        .collect();
+
+    for param in &mut generics.params {
+        if let syn::GenericParam::Type(type_param) = param {
+            type_param.bounds.push(syn::parse_quote!(Group));
+        }
+    }
+
+    let where_clause = generics.make_where_clause();
+    for ident in &type_params {
+        where_clause
+            .predicates
+            .push(syn::parse_quote!(<#ident as Group>::Err: Error + Send));
+    }
+
+    generics
+}
+
+#[proc_macro_derive(Group, attributes(group))]
+pub fn derive_group(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = &input.ident;
+    let generics = add_group_bounds(&input.generics);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => panic!("#[derive(Group)] only supports structs with named fields"),
+        },
+        _ => panic!("#[derive(Group)] only supports structs"),
+    };
+
+    let loads = fields.iter().map(|field| {
+        let ident = field.ident.as_ref().expect("named field");
+        let name_str = ident.to_string();
+
+        let load = match field_count(field) {
+            Some(count) => quote! { src.read_field(#name_str, |src| src.read_seq(#count, Group::decode)) },
+            None => quote! { src.read_field(#name_str, Group::decode) },
+        };
+
+        quote! {
+            let #ident = #load.map_err(move |err| StructGroupError::new(#name_str, err))?;
+        }
+    });
+
+    let field_names = fields
+        .iter()
+        .map(|field| field.ident.as_ref().expect("named field"));
+
+    let expanded = quote! {
+        impl #impl_generics Group for #name #ty_generics #where_clause {
+            type Err = StructGroupError;
+
+            fn decode<F: Format>(src: &mut F) -> Result<Self, Self::Err> {
+                #(#loads)*
+
+                Ok(Self {
+                    #(#field_names,)*
+                })
+            }
+        }
+    };
+
+    expanded.into()
+}
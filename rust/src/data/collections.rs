@@ -0,0 +1,276 @@
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+use derive_more::From;
+
+use crate::data::format::{Format, FormatError};
+use crate::data::group::Group;
+
+/// An error loading one element of a [`Counted`], tagged with the index of
+/// the element that failed.
+#[derive(Debug)]
+pub struct SeqElementError {
+    index: usize,
+    error: Box<Error + Send>,
+}
+
+impl SeqElementError {
+    pub fn new<E: Error + Send + 'static>(index: usize, error: E) -> Self {
+        SeqElementError {
+            index,
+            error: Box::new(error),
+        }
+    }
+}
+
+impl Display for SeqElementError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "error loading element at index {}: {}",
+            self.index, self.error
+        )
+    }
+}
+
+impl Error for SeqElementError {
+    fn cause(&self) -> Option<&Error> {
+        Some(self.error.as_ref())
+    }
+}
+
+impl From<FormatError> for SeqElementError {
+    fn from(err: FormatError) -> Self {
+        SeqElementError::new(0, err)
+    }
+}
+
+/// Either the leading count failed to parse, or one of the elements it
+/// introduced did.
+#[derive(Debug, From)]
+pub enum CountedError {
+    Count(FormatError),
+    Element(SeqElementError),
+}
+
+impl Display for CountedError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            CountedError::Count(err) => write!(f, "error reading element count: {}", err),
+            CountedError::Element(err) => err.fmt(f),
+        }
+    }
+}
+
+impl Error for CountedError {
+    fn cause(&self) -> Option<&Error> {
+        match self {
+            CountedError::Count(err) => Some(err),
+            CountedError::Element(err) => Some(err),
+        }
+    }
+}
+
+/// A sequence preceded by its own length: reads a `usize`, then that many
+/// `T`s. The common "N, then N items" Code Jam shape.
+#[derive(Debug, Clone)]
+pub struct Counted<T>(pub Vec<T>);
+
+impl<T: Group> Group for Counted<T>
+where
+    T::Err: Error + Send + 'static,
+{
+    type Err = CountedError;
+
+    fn decode<F: Format>(src: &mut F) -> Result<Self, Self::Err> {
+        let len = src.read_len().map_err(CountedError::Count)?;
+        let mut index = 0;
+
+        let items = src.read_seq(len, |src| {
+            let item = T::decode(src).map_err(|err| SeqElementError::new(index, err))?;
+            index += 1;
+            Ok(item)
+        })?;
+
+        Ok(Counted(items))
+    }
+}
+
+/// An error loading one cell of a [`Grid`], tagged with the row and column
+/// that failed.
+#[derive(Debug)]
+pub struct GridElementError {
+    row: usize,
+    col: usize,
+    error: Box<Error + Send>,
+}
+
+impl GridElementError {
+    pub fn new<E: Error + Send + 'static>(row: usize, col: usize, error: E) -> Self {
+        GridElementError {
+            row,
+            col,
+            error: Box::new(error),
+        }
+    }
+}
+
+impl Display for GridElementError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "error loading cell ({}, {}): {}",
+            self.row, self.col, self.error
+        )
+    }
+}
+
+impl Error for GridElementError {
+    fn cause(&self) -> Option<&Error> {
+        Some(self.error.as_ref())
+    }
+}
+
+impl From<FormatError> for GridElementError {
+    fn from(err: FormatError) -> Self {
+        GridElementError::new(0, 0, err)
+    }
+}
+
+/// Either a row/column count failed to parse, or one of the cells it
+/// introduced did. `Rows` and `Cols` share the same error type, so this
+/// can't use `#[derive(From)]` (it would need two conflicting
+/// `From<FormatError>` impls); `Element`'s `From<GridElementError>` is
+/// written out by hand below for the same reason.
+#[derive(Debug)]
+pub enum GridError {
+    Rows(FormatError),
+    Cols(FormatError),
+    Element(GridElementError),
+}
+
+impl From<GridElementError> for GridError {
+    fn from(err: GridElementError) -> Self {
+        GridError::Element(err)
+    }
+}
+
+impl From<FormatError> for GridError {
+    fn from(err: FormatError) -> Self {
+        GridError::Element(GridElementError::from(err))
+    }
+}
+
+impl Display for GridError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            GridError::Rows(err) => write!(f, "error reading row count: {}", err),
+            GridError::Cols(err) => write!(f, "error reading column count: {}", err),
+            GridError::Element(err) => err.fmt(f),
+        }
+    }
+}
+
+impl Error for GridError {
+    fn cause(&self) -> Option<&Error> {
+        match self {
+            GridError::Rows(err) => Some(err),
+            GridError::Cols(err) => Some(err),
+            GridError::Element(err) => Some(err),
+        }
+    }
+}
+
+/// A 2D grid preceded by its dimensions: reads `rows`, then `cols`, then
+/// `rows * cols` elements in row-major order as `Vec<Vec<T>>`. The common
+/// "R by C grid" Code Jam shape.
+#[derive(Debug, Clone)]
+pub struct Grid<T>(pub Vec<Vec<T>>);
+
+impl<T: Group> Group for Grid<T>
+where
+    T::Err: Error + Send + 'static,
+{
+    type Err = GridError;
+
+    fn decode<F: Format>(src: &mut F) -> Result<Self, Self::Err> {
+        let rows = src.read_len().map_err(GridError::Rows)?;
+        let cols = src.read_nested_len(rows).map_err(GridError::Cols)?;
+
+        let mut row = 0;
+        let grid = src.read_seq(rows, |src| {
+            let mut col = 0;
+
+            let line = src.read_seq(cols, |src| {
+                let item = T::decode(src).map_err(|err| GridElementError::new(row, col, err))?;
+                col += 1;
+                Ok(item)
+            })
+            // A cell missing from a short row fails inside `read_elem`, before
+            // the closure above can tag it; `row`/`col` still name that cell.
+            .map_err(|err| GridElementError { error: err.error, row, col })?;
+
+            row += 1;
+            Ok(line)
+        })?;
+
+        Ok(Grid(grid))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::*;
+    use crate::data::format::JsonFormat;
+    use crate::data::group::StructGroupError;
+
+    // A minimal stand-in for what `struct_groups!`/`#[derive(Group)]`
+    // expand to, so this test exercises the exact `read_field` call shape
+    // they use without depending on either macro.
+    #[derive(Debug, PartialEq)]
+    struct Point {
+        x: i32,
+        y: i32,
+    }
+
+    impl Group for Point {
+        type Err = StructGroupError;
+
+        fn decode<F: Format>(src: &mut F) -> Result<Self, Self::Err> {
+            let x = src
+                .read_field("x", Group::decode)
+                .map_err(|err| StructGroupError::new("x", err))?;
+            let y = src
+                .read_field("y", Group::decode)
+                .map_err(|err| StructGroupError::new("y", err))?;
+
+            Ok(Point { x, y })
+        }
+    }
+
+    #[test]
+    fn json_format_decodes_a_struct_field_by_field() {
+        let value: Value = serde_json::from_str(r#"{"x": 1, "y": 2}"#).unwrap();
+        let point = Point::decode(&mut JsonFormat::new(&value)).unwrap();
+        assert_eq!(point, Point { x: 1, y: 2 });
+    }
+
+    #[test]
+    fn json_format_decodes_counted() {
+        let value: Value = serde_json::from_str("[10, 20, 30]").unwrap();
+        let Counted(items) = Counted::<i32>::decode(&mut JsonFormat::new(&value)).unwrap();
+        assert_eq!(items, vec![10, 20, 30]);
+    }
+
+    #[test]
+    fn json_format_decodes_a_non_square_grid() {
+        // Regression test: `cols` used to come from re-reading the outer
+        // array's own length (equal to `rows`) instead of the first row's
+        // length, so a non-square grid decoded wrong.
+        let value: Value = serde_json::from_str("[[1, 2, 3], [4, 5, 6]]").unwrap();
+        let Grid(rows) = Grid::<i32>::decode(&mut JsonFormat::new(&value)).unwrap();
+        assert_eq!(rows, vec![vec![1, 2, 3], vec![4, 5, 6]]);
+    }
+}
@@ -0,0 +1,394 @@
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use num_traits::Float;
+use serde_json::Value;
+
+use crate::tokens::Tokens;
+
+/// A source of primitive values that a [`Group`](crate::data::Group) can be
+/// decoded from.
+///
+/// `Tokens` (whitespace-delimited stdin) is the format this crate started
+/// with; `Format` pulls its primitive operations out into a trait so that
+/// other encodings (JSON, CSV, ...) can back the exact same `Group` impls.
+/// Each method corresponds to one "shape" a format needs to be able to
+/// produce: an integer, a float, a string, a positionally-addressed
+/// element, or a named field. `Tokens` has no notion of position or field
+/// names, so a whitespace source just keeps reading the next token
+/// regardless of which method is called; a tree-shaped format like JSON
+/// uses the position/name to descend into the right child value, which is
+/// what lets the same `Group` impl decode from either.
+pub trait Format: Sized {
+    fn read_int<T>(&mut self) -> Result<T, FormatError>
+    where
+        T: FromStr,
+        T::Err: Error + Send + 'static;
+
+    fn read_float<T>(&mut self) -> Result<T, FormatError>
+    where
+        T: FromStr + Float,
+        T::Err: Error + Send + 'static;
+
+    fn read_str(&mut self) -> Result<String, FormatError>;
+
+    /// Read the length that precedes a dynamically-sized sequence (the
+    /// leading `usize` in the `Counted`/`Grid` "N, then N items" shape).
+    /// The default just reads it as an ordinary integer, matching the
+    /// whitespace `Tokens` format; a format whose sequences already carry
+    /// their own length (a JSON array) can report it directly instead of
+    /// expecting a redundant on-the-wire count.
+    fn read_len(&mut self) -> Result<usize, FormatError> {
+        self.read_int()
+    }
+
+    /// Read the second of two leading lengths that precede a nested
+    /// sequence (`Grid`'s row count, then column count, before any rows
+    /// are read). `rows` is the length `read_len` already returned; it's
+    /// ignored by the default, which just reads another ordinary integer,
+    /// matching the whitespace `Tokens` format's separate on-the-wire
+    /// count. A tree-shaped format can't do that: re-deriving `read_len`
+    /// on the same un-advanced value would report the outer sequence's
+    /// own length again (the row count, not the column count), so it
+    /// overrides this to look at `rows`' first element instead.
+    fn read_nested_len(&mut self, rows: usize) -> Result<usize, FormatError> {
+        let _ = rows;
+        self.read_len()
+    }
+
+    /// Read the element at `index` of a positionally-addressed sequence
+    /// (a tuple field, or one element of a `Counted`/`Grid`), decoding it
+    /// with `elem`. The default simply calls `elem` on `self`, which is
+    /// correct for `Tokens`: there's no structure to descend into, just
+    /// the next token. A tree-shaped format overrides this to hand `elem`
+    /// the sub-value at `index` instead of the whole sequence, so that
+    /// repeated calls each see their own element rather than all reading
+    /// the same un-advanced value.
+    fn read_elem<T, E>(
+        &mut self,
+        index: usize,
+        elem: impl FnOnce(&mut Self) -> Result<T, E>,
+    ) -> Result<T, E>
+    where
+        E: From<FormatError>,
+    {
+        let _ = index;
+        elem(self)
+    }
+
+    /// Read exactly `len` elements, decoding each with `elem`. `elem`'s error
+    /// type is left generic (rather than fixed to `FormatError`) so this
+    /// composes directly with `Group::decode`, whose `Err` is whatever the
+    /// element type declares (`TokenError<_>`, `StructGroupError`, ...).
+    /// Built on top of `read_elem`, so formats only need to override that
+    /// one method to get sequences of any length right.
+    fn read_seq<T, E>(
+        &mut self,
+        len: usize,
+        mut elem: impl FnMut(&mut Self) -> Result<T, E>,
+    ) -> Result<Vec<T>, E>
+    where
+        E: From<FormatError>,
+    {
+        (0..len).map(|i| self.read_elem(i, &mut elem)).collect()
+    }
+
+    /// Read the named field of a record (what `struct_groups!` and
+    /// `#[derive(Group)]` call once per field), decoding it with `field`.
+    /// The default simply calls `field` on `self`, ignoring `name`, which
+    /// is correct for `Tokens`: fields have no names on the wire, just a
+    /// sequence of tokens in declaration order. A format with named
+    /// structure (JSON) overrides this to look `name` up and hand `field`
+    /// that sub-value instead of the whole record.
+    fn read_field<T, E>(
+        &mut self,
+        name: &'static str,
+        field: impl FnOnce(&mut Self) -> Result<T, E>,
+    ) -> Result<T, E>
+    where
+        E: From<FormatError>,
+    {
+        let _ = name;
+        field(self)
+    }
+}
+
+/// The error type produced by a [`Format`]'s primitive readers. Wraps
+/// whatever underlying error occurred (a parse failure, an exhausted
+/// source, a malformed record, ...) along with a short description of what
+/// was being read.
+#[derive(Debug)]
+pub struct FormatError {
+    context: &'static str,
+    error: Box<Error + Send>,
+}
+
+impl FormatError {
+    pub fn new<E: Error + Send + 'static>(context: &'static str, error: E) -> Self {
+        FormatError {
+            context,
+            error: Box::new(error),
+        }
+    }
+}
+
+impl Display for FormatError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "error reading {}: {}", self.context, self.error)
+    }
+}
+
+impl Error for FormatError {
+    fn cause(&self) -> Option<&Error> {
+        Some(self.error.as_ref())
+    }
+}
+
+/// The original `Format`: read whitespace-delimited tokens one at a time
+/// from a `Tokens` source. `read_int`/`read_float`/`read_str` all bottom out
+/// in the same `next_raw` + `FromStr::parse` call that `Group::from_tokens`
+/// used before `Format` existed, so whitespace input parses identically.
+pub struct TokensFormat<'a, T: Tokens + 'a>(pub &'a mut T);
+
+impl<'a, T: Tokens> TokensFormat<'a, T> {
+    fn next_raw(&mut self, context: &'static str) -> Result<&str, FormatError> {
+        self.0
+            .next_raw()
+            .map_err(|err| FormatError::new(context, err))
+    }
+}
+
+impl<'a, T: Tokens> Format for TokensFormat<'a, T> {
+    fn read_int<I>(&mut self) -> Result<I, FormatError>
+    where
+        I: FromStr,
+        I::Err: Error + Send + 'static,
+    {
+        let raw = self.next_raw("integer")?;
+        raw.parse()
+            .map_err(|err| FormatError::new("integer", TokenParseError::new(raw, err)))
+    }
+
+    fn read_float<I>(&mut self) -> Result<I, FormatError>
+    where
+        I: FromStr + Float,
+        I::Err: Error + Send + 'static,
+    {
+        let raw = self.next_raw("float")?;
+        raw.parse()
+            .map_err(|err| FormatError::new("float", TokenParseError::new(raw, err)))
+    }
+
+    fn read_str(&mut self) -> Result<String, FormatError> {
+        self.next_raw("string").map(String::from)
+    }
+}
+
+/// Wraps a parse failure with the raw token text that failed to parse, so
+/// a bad int/float on whitespace input still says what the offending text
+/// was (`"abc": invalid digit found in string`) rather than just the
+/// underlying parse error. `JsonFormat` has no equivalent literal token to
+/// quote here — its `read_int`/`read_float` report a value of the wrong
+/// JSON type via `JsonShapeError`, which shows that value, and a number
+/// that doesn't fit the target type with the bare parse error only.
+#[derive(Debug)]
+struct TokenParseError<E> {
+    tok: String,
+    err: E,
+}
+
+impl<E> TokenParseError<E> {
+    fn new(tok: &str, err: E) -> Self {
+        TokenParseError {
+            tok: tok.to_string(),
+            err,
+        }
+    }
+}
+
+impl<E: Display> Display for TokenParseError<E> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "\"{}\": {}", self.tok, self.err)
+    }
+}
+
+impl<E: Error> Error for TokenParseError<E> {
+    fn cause(&self) -> Option<&Error> {
+        Some(&self.err)
+    }
+}
+
+/// A `Format` backed by a parsed `serde_json::Value` tree, demonstrating
+/// that the same `Group`/`struct_groups!` definitions can be decoded from a
+/// JSON representation of a case with no changes. `read_elem` descends into
+/// a JSON array by index, and `read_field` descends into a JSON object by
+/// key, so every `Group` that decodes more than one primitive (a tuple, a
+/// struct, `Counted`, `Grid`) gets its own sub-value per field/element
+/// instead of every read seeing the same un-advanced value.
+pub struct JsonFormat<'a> {
+    value: &'a Value,
+}
+
+impl<'a> JsonFormat<'a> {
+    pub fn new(value: &'a Value) -> Self {
+        JsonFormat { value }
+    }
+
+    fn child(&self, context: &'static str, value: Option<&'a Value>) -> Result<JsonFormat<'a>, FormatError> {
+        value
+            .map(JsonFormat::new)
+            .ok_or_else(|| FormatError::new(context, JsonShapeError::new(context, self.value)))
+    }
+}
+
+impl<'a> Format for JsonFormat<'a> {
+    fn read_int<I>(&mut self) -> Result<I, FormatError>
+    where
+        I: FromStr,
+        I::Err: Error + Send + 'static,
+    {
+        // `as_i64` alone rejects valid unsigned values above `i64::MAX`
+        // (e.g. a `u64`/`u128`/`usize` field), so fall back to `as_u64`
+        // before giving up.
+        self.value
+            .as_i64()
+            .map(|n| n.to_string())
+            .or_else(|| self.value.as_u64().map(|n| n.to_string()))
+            .ok_or_else(|| FormatError::new("integer", JsonShapeError::new("integer", self.value)))
+            .and_then(|raw| raw.parse().map_err(|err| FormatError::new("integer", err)))
+    }
+
+    fn read_float<I>(&mut self) -> Result<I, FormatError>
+    where
+        I: FromStr + Float,
+        I::Err: Error + Send + 'static,
+    {
+        self.value
+            .as_f64()
+            .ok_or_else(|| FormatError::new("float", JsonShapeError::new("float", self.value)))
+            .and_then(|n| {
+                n.to_string()
+                    .parse()
+                    .map_err(|err| FormatError::new("float", err))
+            })
+    }
+
+    fn read_str(&mut self) -> Result<String, FormatError> {
+        self.value
+            .as_str()
+            .map(String::from)
+            .ok_or_else(|| FormatError::new("string", JsonShapeError::new("string", self.value)))
+    }
+
+    fn read_len(&mut self) -> Result<usize, FormatError> {
+        // A JSON array already carries its own length; there's no separate
+        // on-the-wire count to read, unlike the whitespace `Tokens` format.
+        self.value
+            .as_array()
+            .map(|items| items.len())
+            .ok_or_else(|| FormatError::new("array", JsonShapeError::new("array", self.value)))
+    }
+
+    fn read_nested_len(&mut self, rows: usize) -> Result<usize, FormatError> {
+        // Re-reading `read_len` here would report the outer array's own
+        // length again (the row count), not the column count: a JSON grid
+        // is `[[...], [...], ...]`, so the column count only exists as the
+        // length of one of the inner arrays. An empty grid has no row to
+        // look at, but then `cols` is never actually used to read
+        // anything, so any value is fine.
+        if rows == 0 {
+            return Ok(0);
+        }
+
+        let items = self
+            .value
+            .as_array()
+            .ok_or_else(|| FormatError::new("array", JsonShapeError::new("array", self.value)))?;
+
+        items
+            .get(0)
+            .and_then(Value::as_array)
+            .map(|row| row.len())
+            .ok_or_else(|| FormatError::new("grid row", JsonShapeError::new("array", self.value)))
+    }
+
+    fn read_elem<T, E>(
+        &mut self,
+        index: usize,
+        elem: impl FnOnce(&mut Self) -> Result<T, E>,
+    ) -> Result<T, E>
+    where
+        E: From<FormatError>,
+    {
+        let items = self
+            .value
+            .as_array()
+            .ok_or_else(|| FormatError::new("array", JsonShapeError::new("array", self.value)))?;
+
+        let mut child = self.child("array element", items.get(index))?;
+        elem(&mut child)
+    }
+
+    fn read_field<T, E>(
+        &mut self,
+        name: &'static str,
+        field: impl FnOnce(&mut Self) -> Result<T, E>,
+    ) -> Result<T, E>
+    where
+        E: From<FormatError>,
+    {
+        let object = self
+            .value
+            .as_object()
+            .ok_or_else(|| FormatError::new("object", JsonShapeError::new("object", self.value)))?;
+
+        let mut child = self.child(name, object.get(name))?;
+        field(&mut child)
+    }
+}
+
+#[derive(Debug)]
+struct JsonShapeError {
+    expected: &'static str,
+    found: Value,
+}
+
+impl JsonShapeError {
+    fn new(expected: &'static str, found: &Value) -> Self {
+        JsonShapeError {
+            expected,
+            found: found.clone(),
+        }
+    }
+}
+
+impl Display for JsonShapeError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "expected a JSON {}, found {}", self.expected, self.found)
+    }
+}
+
+impl Error for JsonShapeError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::group::Group;
+
+    #[test]
+    fn json_format_reads_integers_above_i64_max() {
+        let value: Value = serde_json::from_str("18446744073709551615").unwrap();
+        let got: u64 = JsonFormat::new(&value).read_int().unwrap();
+        assert_eq!(got, u64::max_value());
+    }
+
+    #[test]
+    fn json_format_advances_through_a_tuple() {
+        // Each element of the tuple must see its own array slot, not the
+        // whole `[1, 2]` un-advanced on every read.
+        let value: Value = serde_json::from_str("[1, 2]").unwrap();
+        let pair: (i32, i32) = Group::decode(&mut JsonFormat::new(&value)).unwrap();
+        assert_eq!(pair, (1, 2));
+    }
+}
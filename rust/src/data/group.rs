@@ -7,12 +7,17 @@ use derive_more::*;
 use ordered_float::{NotNan, OrderedFloat, ParseNotNanError};
 use num_traits::Float;
 
+use crate::data::format::{Format, FormatError, TokensFormat};
 use crate::tokens::{LoadError, Tokens};
 
 pub trait Group: Sized {
     type Err: Error + 'static;
 
-    fn from_tokens(tokens: &mut impl Tokens) -> Result<Self, Self::Err>;
+    fn decode<F: Format>(src: &mut F) -> Result<Self, Self::Err>;
+
+    fn from_tokens(tokens: &mut impl Tokens) -> Result<Self, Self::Err> {
+        Self::decode(&mut TokensFormat(tokens))
+    }
 }
 
 // TOKEN TYPES
@@ -20,6 +25,7 @@ pub trait Group: Sized {
 pub enum TokenError<E: Error> {
     LoadError(LoadError),
     ParseError { err: E, tok: String },
+    Format(FormatError),
 }
 
 impl<E: Error> Display for TokenError<E> {
@@ -29,6 +35,7 @@ impl<E: Error> Display for TokenError<E> {
             TokenError::ParseError { err, tok } => {
                 write!(f, "error parsing token \"{}\": {}", tok, err)
             }
+            TokenError::Format(err) => err.fmt(f),
         }
     }
 }
@@ -38,6 +45,7 @@ impl<E: Error> Error for TokenError<E> {
         match self {
             TokenError::LoadError(err) => Some(err),
             TokenError::ParseError { err, .. } => Some(err),
+            TokenError::Format(err) => Some(err),
         }
     }
 }
@@ -50,9 +58,11 @@ impl<T: FromStr> Group for ViaFromStr<T>
 {
     type Err = TokenError<T::Err>;
 
-    fn from_tokens(tokens: &mut impl Tokens) -> Result<Self, Self::Err> {
-        let raw = tokens.next_raw()?;
-        raw.parse().map_err(move |err| TokenError::ParseError { err, tok: raw.into()})
+    fn decode<F: Format>(src: &mut F) -> Result<Self, Self::Err> {
+        let raw = src.read_str().map_err(TokenError::Format)?;
+        raw.parse()
+            .map(ViaFromStr)
+            .map_err(move |err| TokenError::ParseError { err, tok: raw })
     }
 }
 
@@ -62,33 +72,61 @@ macro_rules! token_via_fromstr {
         impl Group for $type {
             type Err = TokenError<<$type as std::str::FromStr>::Err>;
 
-            fn from_tokens(tokens: &mut impl Tokens) -> Result<Self, Self::Err> {
-                ViaFromStr::from_tokens(tokens).map(|value| value.0)
+            fn decode<F: $crate::data::Format>(src: &mut F) -> Result<Self, Self::Err> {
+                ViaFromStr::decode(src).map(|value| value.0)
+            }
+        }
+    )*}
+}
+
+// Built-in integer and float primitives go straight through `read_int`/
+// `read_float` rather than the generic `token_via_fromstr!` path, so that
+// formats like JSON can decode them from their native numeric
+// representation instead of a stringified token.
+macro_rules! int_via_format {
+    ( $( $type:ident )+ ) => {$(
+        impl Group for $type {
+            type Err = TokenError<<$type as std::str::FromStr>::Err>;
+
+            fn decode<F: Format>(src: &mut F) -> Result<Self, Self::Err> {
+                src.read_int().map_err(TokenError::Format)
             }
         }
     )*}
 }
 
-token_via_fromstr!{
+macro_rules! float_via_format {
+    ( $( $type:ident )+ ) => {$(
+        impl Group for $type {
+            type Err = TokenError<<$type as std::str::FromStr>::Err>;
+
+            fn decode<F: Format>(src: &mut F) -> Result<Self, Self::Err> {
+                src.read_float().map_err(TokenError::Format)
+            }
+        }
+    )*}
+}
+
+int_via_format!{
     i8 i16 i32 i64 i128 isize
     u8 u16 u32 u64 u128 usize
-    f32 f64
-    char String
 }
+float_via_format!{f32 f64}
+token_via_fromstr!{char String}
 
 impl<T: Group + Float> Group for OrderedFloat<T> {
     type Err = T::Err;
 
-    fn from_tokens(tokens: &mut impl Tokens) -> Result<Self, Self::Err> {
-        T::from_tokens(tokens).map(OrderedFloat)
+    fn decode<F: Format>(src: &mut F) -> Result<Self, Self::Err> {
+        T::decode(src).map(OrderedFloat)
     }
 }
 
 impl<T: Group + Float> Group for NotNan<T> {
     type Err = ParseNotNanError<T::Err>;
 
-    fn from_tokens(tokens: &mut impl Tokens) -> Result<Self, Self::Err> {
-        T::from_tokens(tokens)
+    fn decode<F: Format>(src: &mut F) -> Result<Self, Self::Err> {
+        T::decode(src)
             .map_err(ParseNotNanError::ParseFloatError)
             .and_then(|value| NotNan::new(value).map_err(|_| ParseNotNanError::IsNaN))
     }
@@ -100,7 +138,7 @@ pub type UsizeTokenError = <usize as Group>::Err;
 impl Group for () {
     type Err = !;
 
-    fn from_tokens(_tokens: &mut impl Tokens) -> Result<(), !> {
+    fn decode<F: Format>(_src: &mut F) -> Result<(), !> {
         Ok(())
     }
 }
@@ -143,6 +181,12 @@ impl From<!> for TupleGroupError {
     }
 }
 
+impl From<FormatError> for TupleGroupError {
+    fn from(err: FormatError) -> Self {
+        TupleGroupError::new(0, err)
+    }
+}
+
 macro_rules! count {
     () => (0);
     ($thing:ident $(, $rest:ident)*) => (1 + count!($($rest),*))
@@ -160,9 +204,10 @@ macro_rules! tuple_group {
         {
             type Err = TupleGroupError;
 
-            fn from_tokens(tokens: &mut impl Tokens) -> Result<Self, Self::Err> {
-                let ($($tail,)*) = tokens.next()?;
-                let last = tokens.next().map_err(|err| TupleGroupError::new(count!($($tail),*), err))?;
+            fn decode<F: Format>(src: &mut F) -> Result<Self, Self::Err> {
+                let ($($tail,)*) = Group::decode(src)?;
+                let last = src.read_elem(count!($($tail),*), Group::decode)
+                    .map_err(|err| TupleGroupError::new(count!($($tail),*), err))?;
 
                 Ok(($($tail,)*  last))
             }
@@ -203,13 +248,19 @@ impl Error for StructGroupError {
     }
 }
 
+impl From<FormatError> for StructGroupError {
+    fn from(err: FormatError) -> Self {
+        StructGroupError::new("<format>", err)
+    }
+}
+
 #[macro_export]
 macro_rules! load_field {
-    ($tokens:ident) => {
-        $tokens.next()
+    ($src:ident, $name:expr) => {
+        $src.read_field($name, $crate::data::Group::decode)
     };
-    ($tokens:ident => $size:expr) => {
-        $tokens.collect($size)
+    ($src:ident, $name:expr => $size:expr) => {
+        $src.read_field($name, |src| src.read_seq($size, $crate::data::Group::decode))
     };
 }
 
@@ -228,9 +279,9 @@ macro_rules! struct_groups {
         impl $crate::data::Group for $Name {
             type Err = $crate::data::StructGroupError;
 
-            fn from_tokens(tokens: &mut impl Tokens) -> Result<Self, Self::Err> {
+            fn decode<F: $crate::data::Format>(src: &mut F) -> Result<Self, Self::Err> {
                 $(
-                    let $field = load_field!(tokens $(=> $size)*)
+                    let $field = load_field!(src, stringify!($field) $(=> $size)*)
                         .map_err(move |err| Self::Err::new(stringify!($field), err))?;
                 )*
 